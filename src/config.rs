@@ -11,6 +11,50 @@ pub struct OpenApi {
 
     #[serde(default)]
     pub paths: HashMap<String, PathItem>,
+
+    #[serde(default)]
+    pub components: Components,
+
+    /// Global security requirements, overridden by `Operation::security` when present.
+    #[serde(default)]
+    pub security: Vec<SecurityRequirement>,
+}
+
+/// A single `security` entry: scheme name -> required scopes (ignored for apiKey/http).
+pub type SecurityRequirement = HashMap<String, Vec<String>>;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Components {
+    #[serde(default, rename = "securitySchemes")]
+    pub security_schemes: HashMap<String, SecurityScheme>,
+
+    #[serde(default)]
+    pub schemas: HashMap<String, Schema>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityScheme {
+    /// "apiKey", "http", or anything else (unsupported types are skipped, not rejected).
+    #[serde(rename = "type")]
+    pub ty: String,
+
+    /// apiKey: the header/query parameter name.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// apiKey: "header" or "query".
+    #[serde(rename = "in")]
+    #[serde(default)]
+    pub location: Option<String>,
+
+    /// http: "bearer", "basic", etc.
+    #[serde(default)]
+    pub scheme: Option<String>,
+
+    /// Allow-list of accepted credential values. Empty means "any non-empty credential".
+    #[serde(rename = "x-mirage-valid-keys")]
+    #[serde(default)]
+    pub valid_keys: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -45,6 +89,19 @@ pub struct Operation {
 
     #[serde(default)]
     pub responses: HashMap<String, Response>,
+
+    /// Overrides the top-level `OpenApi::security` when present; `Some(vec![])` disables auth.
+    #[serde(default)]
+    pub security: Option<Vec<SecurityRequirement>>,
+
+    #[serde(default, rename = "requestBody")]
+    pub request_body: Option<RequestBody>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestBody {
+    #[serde(default)]
+    pub content: HashMap<String, MediaType>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -55,7 +112,23 @@ pub struct Response {
 
     #[serde(default)]
     pub content: HashMap<String, MediaType>,
+
+    #[serde(default)]
+    pub headers: HashMap<String, ResponseHeader>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseHeader {
+    /// Literal value to emit, e.g. `x-mirage-value: "v1.2.3"`.
+    #[serde(rename = "x-mirage-value")]
+    #[serde(default)]
+    pub value: Option<String>,
+
+    /// Falls back to a schema-derived default when no `x-mirage-value` is set.
+    #[serde(default)]
+    pub schema: Option<Schema>,
 }
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct MediaType {
     /// Single example: `example: {...}`
@@ -73,6 +146,17 @@ pub struct MediaType {
     #[serde(rename = "x-mirage-example-param")]
     #[serde(default)]
     pub example_param: Option<String>,
+
+    /// Like `x-mirage-example-param`, but keyed off a request header instead of a query param.
+    #[serde(rename = "x-mirage-example-header")]
+    #[serde(default)]
+    pub example_header: Option<String>,
+
+    /// Marks this media type as JSON-RPC 2.0: dispatch by the body's `method` instead of
+    /// matching a REST path/verb.
+    #[serde(rename = "x-mirage-jsonrpc")]
+    #[serde(default)]
+    pub jsonrpc: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -88,6 +172,11 @@ pub struct Example {
 #[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
 pub struct Schema {
+    /// `$ref: '#/components/schemas/Name'` — when set, the other fields are ignored.
+    #[serde(rename = "$ref")]
+    #[serde(default)]
+    pub reference: Option<String>,
+
     /// Type: "object", "array", "string", "number", "integer", "boolean"
     #[serde(rename = "type")]
     pub ty: Option<String>,
@@ -108,6 +197,55 @@ pub struct Schema {
     /// Format: "date-time", "uuid", etc. (used to generate nicer mocks)
     #[serde(default)]
     pub format: Option<String>,
+
+    /// `allOf: [...]` — subschemas are deep-merged into one object.
+    #[serde(rename = "allOf")]
+    #[serde(default)]
+    pub all_of: Vec<Schema>,
+
+    /// `oneOf: [...]` — the first branch is used.
+    #[serde(rename = "oneOf")]
+    #[serde(default)]
+    pub one_of: Vec<Schema>,
+
+    /// `anyOf: [...]` — the first branch is used.
+    #[serde(rename = "anyOf")]
+    #[serde(default)]
+    pub any_of: Vec<Schema>,
+
+    /// Numeric bounds: generated numbers/integers are clamped into `[minimum, maximum]`.
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub maximum: Option<f64>,
+
+    /// String length bounds: generated strings are padded/truncated to fit.
+    #[serde(rename = "minLength")]
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(rename = "maxLength")]
+    #[serde(default)]
+    pub max_length: Option<usize>,
+
+    /// Array size bounds: generated arrays are sized to fit, repeating the item schema.
+    #[serde(rename = "minItems")]
+    #[serde(default)]
+    pub min_items: Option<usize>,
+    #[serde(rename = "maxItems")]
+    #[serde(default)]
+    pub max_items: Option<usize>,
+
+    /// Regex the generated string should match. Not currently used to drive generation
+    /// (there's no regex-to-string synthesizer here), but parsed so specs keep loading and
+    /// it's available if a `default`/`example` needs to be validated against it later.
+    #[serde(default)]
+    pub pattern: Option<String>,
+
+    /// Inline default/example value, preferred over type-based generation when present.
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub example: Option<Value>,
 }
 
 pub fn load(path: &str) -> Result<OpenApi, Error> {
@@ -1,6 +1,9 @@
 mod config;
 mod error;
+mod metrics;
+mod rng;
 mod server;
+mod store;
 
 use clap::Parser;
 
@@ -19,18 +22,62 @@ struct Cli {
     /// Address to bind the HTTP server to (host:port)
     #[arg(long, default_value = "127.0.0.1:8080")]
     addr: String,
+
+    /// Enable stateful CRUD mode: POST/PUT/PATCH/DELETE mutate an in-memory store that
+    /// subsequent GETs observe, instead of always replaying the spec's examples.
+    #[arg(long, default_value_t = false)]
+    stateful: bool,
+
+    /// Treat every path under this prefix as JSON-RPC 2.0, dispatched by the body's `method`
+    /// rather than REST routing. Operations can also opt in per-media-type via
+    /// `x-mirage-jsonrpc: true`, regardless of this flag.
+    #[arg(long = "jsonrpc")]
+    jsonrpc_prefix: Option<String>,
+
+    /// Seed for the schema-based mock data generator, so repeated requests (and runs) produce
+    /// the same values instead of fixed placeholder constants.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Gzip/brotli-compress response bodies based on the client's Accept-Encoding.
+    #[arg(long, default_value_t = false)]
+    compression: bool,
+
+    /// Log method, matched path template, status, and latency for every request.
+    #[arg(long, default_value_t = false)]
+    verbose: bool,
+
+    /// Bind a Prometheus `/metrics` scrape endpoint on this address (host:port), separate from
+    /// the main listener. Exposes per-path-template/method/status request counts and latency,
+    /// plus a counter for requests that no mock in the spec answered.
+    #[arg(long)]
+    metrics_addr: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), error::Error> {
     let cli = Cli::parse();
 
+    if cli.verbose {
+        tracing_subscriber::fmt::init();
+    }
+
     println!("Mirage API starting with OpenAPI spec: {}", cli.config);
 
     let spec = config::load(&cli.config)?;
     println!("OpenAPI loaded: {} {}", spec.info.title, spec.info.version);
 
-    server::run(spec, cli.addr).await?;
+    server::run(
+        spec,
+        cli.addr,
+        cli.stateful,
+        cli.jsonrpc_prefix,
+        cli.seed,
+        cli.compression,
+        cli.verbose,
+        cli.metrics_addr,
+    )
+    .await?;
 
     Ok(())
 }
@@ -0,0 +1,40 @@
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::time::Duration;
+
+/// Installs the global Prometheus recorder. `record_request`/`record_not_found` below are
+/// no-ops until this has run, so call sites don't need to gate on `--metrics-addr` themselves.
+pub fn install() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .install_recorder()
+        .expect("failed to install Prometheus recorder")
+}
+
+/// Counts and times a request that matched a spec path, labeled by method/path template/status.
+pub fn record_request(method: &str, path_template: &str, status: u16, latency: Duration) {
+    metrics::counter!(
+        "mirage_requests_total",
+        "method" => method.to_string(),
+        "path" => path_template.to_string(),
+        "status" => status.to_string(),
+    )
+    .increment(1);
+
+    metrics::histogram!(
+        "mirage_request_duration_seconds",
+        "method" => method.to_string(),
+        "path" => path_template.to_string(),
+    )
+    .record(latency.as_secs_f64());
+}
+
+/// Counts requests answered with "no mock found": no path in the spec matched, or the matched
+/// path defines no operation or response for the method. Deliberately has no `path` label:
+/// unmatched paths are arbitrary client input, and labeling by them would let a client grow
+/// the Prometheus registry without bound.
+pub fn record_not_found(method: &str) {
+    metrics::counter!(
+        "mirage_unmatched_requests_total",
+        "method" => method.to_string(),
+    )
+    .increment(1);
+}
@@ -0,0 +1,34 @@
+/// Small deterministic xorshift64* PRNG used to vary generated mock values per-field while
+/// staying reproducible across runs when seeded via `--seed`.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Avoid the all-zero state, which xorshift can't escape.
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Rng(if state == 0 { 0x9E37_79B9_7F4A_7C15 } else { state })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in `[0.0, 1.0)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in `[min, max]` (inclusive).
+    pub fn next_range(&mut self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+        let span = (max - min) as u64 + 1;
+        min + (self.next_u64() % span) as i64
+    }
+}
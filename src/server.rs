@@ -8,27 +8,81 @@ use axum::{
     routing::any,
 };
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
+use tower_http::compression::CompressionLayer;
+use tower_http::trace::TraceLayer;
 
-use crate::config::{MediaType, OpenApi, Operation, PathItem, Response, Schema};
+use crate::config::{MediaType, OpenApi, Operation, PathItem, Response, Schema, SecurityScheme};
 use crate::error::Error;
+use crate::rng::Rng;
+use crate::store::Store;
 
 #[derive(Clone)]
 struct AppState {
     spec: OpenApi,
+    stateful: bool,
+    store: Arc<Store>,
+    jsonrpc_prefix: Option<String>,
+    seed: u64,
 }
 
-pub async fn run(spec: OpenApi, addr: String) -> Result<(), Error> {
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    spec: OpenApi,
+    addr: String,
+    stateful: bool,
+    jsonrpc_prefix: Option<String>,
+    seed: u64,
+    compression: bool,
+    verbose: bool,
+    metrics_addr: Option<String>,
+) -> Result<(), Error> {
     let listener = TcpListener::bind(&addr).await?;
 
-    let state = AppState { spec };
+    if let Some(metrics_addr) = metrics_addr {
+        run_metrics_server(metrics_addr).await?;
+    }
 
-    let app = Router::new()
+    let state = AppState {
+        spec,
+        stateful,
+        store: Arc::new(Store::new()),
+        jsonrpc_prefix,
+        seed,
+    };
+
+    let mut app = Router::new()
         // Single catch-all route: any path/method
         .route("/*path", any(handle_request))
         .with_state(state);
 
+    if compression {
+        app = app.layer(CompressionLayer::new());
+    }
+
+    if verbose {
+        app = app.layer(TraceLayer::new_for_http().on_response(
+            |response: &axum::http::Response<_>, latency: Duration, _span: &tracing::Span| {
+                let trace = response.extensions().get::<RequestTrace>();
+                let method = trace.map(|t| t.method.as_str()).unwrap_or("-");
+                let template = trace
+                    .and_then(|t| t.template.as_deref())
+                    .unwrap_or("<unmatched>");
+
+                tracing::info!(
+                    method,
+                    template,
+                    status = %response.status(),
+                    latency_ms = latency.as_millis(),
+                    "handled request"
+                );
+            },
+        ));
+    }
+
     println!("Mirage API listening on http://{}", addr);
 
     axum::serve(listener, app).await?;
@@ -36,29 +90,114 @@ pub async fn run(spec: OpenApi, addr: String) -> Result<(), Error> {
     Ok(())
 }
 
+/// Installs the Prometheus recorder and spawns a background server exposing `/metrics` on its
+/// own listener, kept off the main router so a scraper is never treated as a mock path.
+async fn run_metrics_server(addr: String) -> Result<(), Error> {
+    let handle = crate::metrics::install();
+    let listener = TcpListener::bind(&addr).await?;
+
+    let app = Router::new().route(
+        "/metrics",
+        axum::routing::get(move || {
+            let handle = handle.clone();
+            async move { handle.render() }
+        }),
+    );
+
+    println!("Mirage API metrics listening on http://{}", addr);
+
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            eprintln!("metrics server error: {}", err);
+        }
+    });
+
+    Ok(())
+}
+
 #[allow(clippy::collapsible_if)]
 async fn handle_request(State(state): State<AppState>, req: Request<Body>) -> impl IntoResponse {
+    let start = Instant::now();
     let method = req.method().clone();
     let path = req.uri().path().to_string();
     let query_params = parse_query(req.uri().query());
+    let headers = req.headers().clone();
 
-    // Find PathItem by path template, supporting /users/{id}
-    if let Some((_, path_item)) = state
+    // Find PathItem by path template, supporting /users/{id}. Cloned so we can move `req`
+    // (to read its body in stateful mode) without holding a borrow of `state.spec`.
+    let matched = state
         .spec
         .paths
         .iter()
         .find(|(template, _)| match_path(template, &path))
-    {
-        if let Some(operation) = find_operation_for_method(path_item, &method) {
-            if let Some((status, body, content_type)) =
-                build_response_from_operation(operation, &query_params)
-            {
-                return build_response(status, body, content_type);
+        .map(|(template, path_item)| (template.clone(), path_item.clone()));
+
+    let (mut response, template_used) = 'resp: {
+        if let Some((template, path_item)) = matched {
+            tracing::debug!(%method, %path, matched_template = %template, "matched path template");
+
+            if let Some(operation) = find_operation_for_method(&path_item, &method) {
+                if let Some(denied) = enforce_auth(&state.spec, operation, &headers, &query_params)
+                {
+                    break 'resp (denied, Some(template));
+                }
+
+                if method == Method::POST && is_jsonrpc_operation(operation, &state, &path) {
+                    break 'resp (handle_jsonrpc(operation, req).await, Some(template));
+                }
+
+                if state.stateful {
+                    if let Some(resp) =
+                        handle_stateful(&state, &template, operation, &method, &path, req).await
+                    {
+                        break 'resp (resp, Some(template));
+                    }
+                }
+
+                if let Some((status, body, content_type, resp_headers)) =
+                    build_response_from_operation(
+                        operation,
+                        &query_params,
+                        &headers,
+                        &state.spec,
+                        state.seed,
+                    )
+                {
+                    break 'resp (
+                        build_response_with_headers(status, body, content_type, &resp_headers),
+                        Some(template),
+                    );
+                }
             }
         }
+
+        (build_not_found_response(method.clone(), path.clone()), None)
+    };
+
+    match &template_used {
+        Some(template) => crate::metrics::record_request(
+            method.as_str(),
+            template,
+            response.status().as_u16(),
+            start.elapsed(),
+        ),
+        None => crate::metrics::record_not_found(method.as_str()),
     }
 
-    build_not_found_response(method, path)
+    response
+        .extensions_mut()
+        .insert(RequestTrace { method, template: template_used });
+
+    response
+}
+
+/// Stashed on the response so `TraceLayer`'s `on_response` hook — which only sees the
+/// response, not the original request — can log the method and matched template alongside
+/// status/latency.
+#[derive(Clone)]
+struct RequestTrace {
+    method: Method,
+    template: Option<String>,
 }
 
 /// Very simple query parser: ?a=1&b=2 → HashMap { "a": "1", "b": "2" }
@@ -127,33 +266,469 @@ fn is_path_param(seg: &str) -> bool {
     seg.starts_with('{') && seg.ends_with('}') && seg.len() > 2
 }
 
+/// Checks the operation's (or the spec's default) security requirements against the incoming
+/// request. Returns `Some(response)` with a 401/403 when the request should be rejected, or
+/// `None` when the request is authorized (or the operation declares no security).
+fn enforce_auth(
+    spec: &OpenApi,
+    operation: &Operation,
+    headers: &axum::http::HeaderMap,
+    query: &HashMap<String, String>,
+) -> Option<axum::response::Response> {
+    let requirements = operation
+        .security
+        .as_ref()
+        .unwrap_or(&spec.security);
+
+    if requirements.is_empty() {
+        return None;
+    }
+
+    let mut credential_missing = false;
+
+    for requirement in requirements {
+        let mut satisfied = true;
+
+        for scheme_name in requirement.keys() {
+            let Some(scheme) = spec.components.security_schemes.get(scheme_name) else {
+                // Unknown scheme reference: don't block the request on it.
+                continue;
+            };
+
+            match extract_credential(scheme, headers, query) {
+                None => {
+                    if is_supported_scheme(scheme) {
+                        satisfied = false;
+                        credential_missing = true;
+                    }
+                }
+                Some(credential) => {
+                    if is_supported_scheme(scheme) && !is_valid_credential(scheme, &credential) {
+                        satisfied = false;
+                    }
+                }
+            }
+        }
+
+        if satisfied {
+            return None;
+        }
+    }
+
+    let status = if credential_missing {
+        StatusCode::UNAUTHORIZED
+    } else {
+        StatusCode::FORBIDDEN
+    };
+
+    Some(build_auth_error_response(status))
+}
+
+/// apiKey and http (bearer) are the scheme types Mirage knows how to validate; anything else
+/// (oauth2, openIdConnect, ...) is treated as unprotected so the spec still loads and serves.
+fn is_supported_scheme(scheme: &SecurityScheme) -> bool {
+    matches!(scheme.ty.as_str(), "apiKey" | "http")
+}
+
+fn extract_credential(
+    scheme: &SecurityScheme,
+    headers: &axum::http::HeaderMap,
+    query: &HashMap<String, String>,
+) -> Option<String> {
+    match scheme.ty.as_str() {
+        "apiKey" => {
+            let name = scheme.name.as_deref()?;
+            match scheme.location.as_deref() {
+                Some("query") => query.get(name).cloned(),
+                _ => headers.get(name)?.to_str().ok().map(|s| s.to_string()),
+            }
+        }
+        "http" => {
+            let value = headers.get(axum::http::header::AUTHORIZATION)?;
+            let value = value.to_str().ok()?;
+            value
+                .strip_prefix("Bearer ")
+                .or_else(|| value.strip_prefix("bearer "))
+                .map(|s| s.to_string())
+                .or_else(|| Some(value.to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn is_valid_credential(scheme: &SecurityScheme, credential: &str) -> bool {
+    if scheme.valid_keys.is_empty() {
+        !credential.is_empty()
+    } else {
+        scheme.valid_keys.iter().any(|k| k == credential)
+    }
+}
+
+fn build_auth_error_response(status: StatusCode) -> axum::response::Response {
+    let message = if status == StatusCode::UNAUTHORIZED {
+        "missing credentials"
+    } else {
+        "invalid credentials"
+    };
+
+    let body = serde_json::json!({ "error": message });
+
+    axum::http::Response::builder()
+        .status(status)
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&body).unwrap_or_default()))
+        .expect("failed to build auth error response")
+}
+
+/// Handles POST/GET/PUT/PATCH/DELETE against `state.store` for `--stateful` mode. Returns
+/// `None` when the request isn't a stateful CRUD operation at all, letting the caller fall
+/// back to example/schema replay. A missing resource for a known GET-by-id or DELETE is a
+/// real 404, not a fallback — PUT/PATCH of a missing id still fall back since there's no
+/// item to merge into.
+async fn handle_stateful(
+    state: &AppState,
+    template: &str,
+    operation: &Operation,
+    method: &Method,
+    path: &str,
+    req: Request<Body>,
+) -> Option<axum::response::Response> {
+    let collection = collection_key(template);
+    let id = extract_id(template, path);
+
+    match *method {
+        Method::GET => {
+            if let Some(id) = id {
+                match state.store.get(&collection, &id) {
+                    Some(item) => Some(json_response(200, item)),
+                    None => Some(json_response(
+                        404,
+                        serde_json::json!({ "error": "not found" }),
+                    )),
+                }
+            } else if is_collection_endpoint(state, template, operation) {
+                let items = state.store.list(&collection);
+                Some(json_response(200, Value::Array(items)))
+            } else {
+                None
+            }
+        }
+        Method::POST => {
+            let schema = operation_json_schema(operation)?;
+            let bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
+                .await
+                .ok()?;
+            let mut value = if bytes.is_empty() {
+                generate_from_schema(schema, &state.spec, state.seed)
+            } else {
+                serde_json::from_slice(&bytes)
+                    .unwrap_or_else(|_| generate_from_schema(schema, &state.spec, state.seed))
+            };
+            assign_id(&mut value, resolve_schema(schema, &state.spec), &state.store);
+            state.store.insert(&collection, value.clone());
+            Some(json_response(201, value))
+        }
+        Method::PUT | Method::PATCH => {
+            let id = id?;
+            let bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
+                .await
+                .ok()?;
+            let patch: Value = serde_json::from_slice(&bytes).ok()?;
+            let updated = state.store.merge(&collection, &id, &patch)?;
+            Some(json_response(200, updated))
+        }
+        Method::DELETE => {
+            let id = id?;
+            if state.store.remove(&collection, &id) {
+                Some(build_response(204, None, "application/json".to_string()))
+            } else {
+                Some(json_response(
+                    404,
+                    serde_json::json!({ "error": "not found" }),
+                ))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// True when this operation's POST body should be dispatched as JSON-RPC 2.0 rather than
+/// matched as a plain REST call: either the path falls under the configured `--jsonrpc`
+/// prefix, or its `application/json` request media type carries `x-mirage-jsonrpc: true`.
+fn is_jsonrpc_operation(operation: &Operation, state: &AppState, path: &str) -> bool {
+    if let Some(prefix) = &state.jsonrpc_prefix {
+        if path.starts_with(prefix.as_str()) {
+            return true;
+        }
+    }
+
+    operation
+        .request_body
+        .as_ref()
+        .and_then(|rb| rb.content.get("application/json"))
+        .map(|mt| mt.jsonrpc)
+        .unwrap_or(false)
+}
+
+/// Parses the POST body as a JSON-RPC 2.0 request (or batch) and dispatches each call by
+/// looking up a response example keyed on its `method`.
+async fn handle_jsonrpc(operation: &Operation, req: Request<Body>) -> axum::response::Response {
+    let Ok(bytes) = axum::body::to_bytes(req.into_body(), usize::MAX).await else {
+        return build_response(400, None, "application/json".to_string());
+    };
+
+    let Ok(body) = serde_json::from_slice::<Value>(&bytes) else {
+        return json_response(
+            200,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32700, "message": "Parse error" },
+                "id": Value::Null,
+            }),
+        );
+    };
+
+    match &body {
+        Value::Array(batch) => {
+            let responses: Vec<Value> = batch
+                .iter()
+                .filter_map(|call| dispatch_jsonrpc_call(call, operation))
+                .collect();
+            json_response(200, Value::Array(responses))
+        }
+        _ => match dispatch_jsonrpc_call(&body, operation) {
+            Some(response) => json_response(200, response),
+            // A lone notification (no `id`) gets no response body at all.
+            None => build_response(204, None, "application/json".to_string()),
+        },
+    }
+}
+
+/// Dispatches one JSON-RPC request object against the operation's 200 response examples.
+/// Returns `None` for notifications, which are omitted from batch responses.
+fn dispatch_jsonrpc_call(call: &Value, operation: &Operation) -> Option<Value> {
+    let id = call.get("id").cloned();
+    let is_notification = id.is_none();
+    let id = id.unwrap_or(Value::Null);
+
+    let method = match call.get("method").and_then(Value::as_str) {
+        Some(method) => method,
+        // A missing/non-string `method` on a true notification (no `id`) has nothing to
+        // reply to; an id-bearing call gets an Invalid Request error rather than being
+        // silently dropped.
+        None if is_notification => return None,
+        None => {
+            return Some(serde_json::json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32600, "message": "Invalid Request" },
+                "id": id,
+            }));
+        }
+    };
+
+    let result = operation
+        .responses
+        .get("200")
+        .and_then(|resp| resp.content.get("application/json"))
+        .and_then(|mt| pick_jsonrpc_result(mt, method));
+
+    let response = match result {
+        Some(value) => serde_json::json!({ "jsonrpc": "2.0", "result": value, "id": id }),
+        None => serde_json::json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "Method not found" },
+            "id": id,
+        }),
+    };
+
+    if is_notification {
+        None
+    } else {
+        Some(response)
+    }
+}
+
+/// Mirrors `pick_example`'s `examples` lookup, but keyed by the JSON-RPC `method` rather than
+/// a query param.
+fn pick_jsonrpc_result(mt: &MediaType, method: &str) -> Option<Value> {
+    mt.examples.get(method)?.value.clone()
+}
+
+fn json_response(status: u16, value: Value) -> axum::response::Response {
+    build_response(status, Some(BodyKind::Json(value)), "application/json".to_string())
+}
+
+/// Strips a trailing `{param}` segment, so `/users/{id}` and `/users` key the same collection.
+fn collection_key(template: &str) -> String {
+    let trimmed = template.trim_end_matches('/');
+    if let Some(last_slash) = trimmed.rfind('/') {
+        if is_path_param(&trimmed[last_slash + 1..]) {
+            return trimmed[..last_slash].to_string();
+        }
+    }
+    trimmed.to_string()
+}
+
+/// Reads the single path-param value out of `actual`, assuming `template` has at most one.
+fn extract_id(template: &str, actual: &str) -> Option<String> {
+    let t_parts: Vec<_> = template
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let a_parts: Vec<_> = actual
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if t_parts.len() != a_parts.len() {
+        return None;
+    }
+
+    t_parts
+        .iter()
+        .zip(a_parts.iter())
+        .find_map(|(t_seg, a_seg)| is_path_param(t_seg).then(|| a_seg.to_string()))
+}
+
+/// The JSON schema describing a resource: prefers the request body, falling back to the
+/// 201/200 response (the common case where a mock spec only documents the response shape).
+fn operation_json_schema(operation: &Operation) -> Option<&Schema> {
+    if let Some(schema) = operation
+        .request_body
+        .as_ref()
+        .and_then(|rb| rb.content.get("application/json"))
+        .and_then(|mt| mt.schema.as_ref())
+    {
+        return Some(schema);
+    }
+
+    operation
+        .responses
+        .get("201")
+        .or_else(|| operation.responses.get("200"))
+        .and_then(|r| r.content.get("application/json"))
+        .and_then(|mt| mt.schema.as_ref())
+}
+
+/// Whether a GET without a path param is a real collection listing rather than a singleton
+/// resource (`GET /status`, `GET /me`, `GET /health`): true when the 200 response schema is an
+/// array, or a sibling POST is defined on the same template (the thing that would populate the
+/// collection). Singleton endpoints fall back to example/schema replay instead of `[]`.
+fn is_collection_endpoint(state: &AppState, template: &str, operation: &Operation) -> bool {
+    let has_sibling_post = state
+        .spec
+        .paths
+        .get(template)
+        .is_some_and(|item| item.post.is_some());
+    if has_sibling_post {
+        return true;
+    }
+
+    operation
+        .responses
+        .get("200")
+        .and_then(|r| r.content.get("application/json"))
+        .and_then(|mt| mt.schema.as_ref())
+        .map(|schema| resolve_schema(schema, &state.spec).ty.as_deref() == Some("array"))
+        .unwrap_or(false)
+}
+
+/// Follows a `$ref` chain to the concrete schema, for call sites (like id-format lookup) that
+/// need to inspect `properties`/`ty` rather than generate a value.
+fn resolve_schema<'a>(schema: &'a Schema, spec: &'a OpenApi) -> &'a Schema {
+    let mut current = schema;
+    let mut seen = HashSet::new();
+
+    while let Some(reference) = &current.reference {
+        if !seen.insert(reference.clone()) {
+            break;
+        }
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        match spec.components.schemas.get(name) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    current
+}
+
+/// Assigns a fresh `id` (following the schema's declared format) unless one was already
+/// supplied in the request body.
+fn assign_id(value: &mut Value, schema: &Schema, store: &Store) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    if map.contains_key("id") {
+        return;
+    }
+
+    let id_schema = schema.properties.get("id");
+    let id_value = match id_schema.and_then(|s| s.format.as_deref()) {
+        Some("uuid") => Value::String(format!("00000000-0000-4000-8000-{:012x}", store.next_id())),
+        _ => match id_schema.and_then(|s| s.ty.as_deref()) {
+            Some("string") => Value::String(store.next_id().to_string()),
+            _ => Value::Number(store.next_id().into()),
+        },
+    };
+    map.insert("id".to_string(), id_value);
+}
+
 #[allow(clippy::collapsible_if)]
 fn build_response_from_operation(
     operation: &Operation,
     query: &HashMap<String, String>,
-) -> Option<(u16, Option<BodyKind>, String)> {
+    headers: &axum::http::HeaderMap,
+    spec: &OpenApi,
+    seed: u64,
+) -> Option<(u16, Option<BodyKind>, String, Vec<(String, String)>)> {
     if operation.responses.is_empty() {
         return None;
     }
 
     // 1) Try to use 200
     if let Some(resp) = operation.responses.get("200") {
-        if let Some(res) = build_body_from_response(resp, query) {
-            return Some((200, res.0, res.1));
+        if let Some(res) = build_body_from_response(resp, query, headers, spec, seed) {
+            return Some((200, res.0, res.1, resolve_response_headers(resp, spec, seed)));
         }
     }
 
     // 2) Otherwise take the first available status code
     if let Some((status_code, resp)) = operation.responses.iter().next() {
         let status = status_code.parse::<u16>().unwrap_or(200);
-        if let Some(res) = build_body_from_response(resp, query) {
-            return Some((status, res.0, res.1));
+        if let Some(res) = build_body_from_response(resp, query, headers, spec, seed) {
+            return Some((status, res.0, res.1, resolve_response_headers(resp, spec, seed)));
         }
     }
 
     None
 }
 
+/// Resolves an OpenAPI `Response.headers` map into concrete header name/value pairs: an
+/// `x-mirage-value` wins outright, otherwise a schema-derived value is generated.
+fn resolve_response_headers(resp: &Response, spec: &OpenApi, seed: u64) -> Vec<(String, String)> {
+    resp.headers
+        .iter()
+        .filter_map(|(name, header)| {
+            let value = if let Some(value) = &header.value {
+                value.clone()
+            } else {
+                header_value_string(&generate_from_schema(header.schema.as_ref()?, spec, seed))
+            };
+            Some((name.clone(), value))
+        })
+        .collect()
+}
+
+fn header_value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 #[derive(Debug)]
 enum BodyKind {
     Json(Value),
@@ -166,14 +741,28 @@ enum BodyKind {
 /// 2) Иначе example.
 /// 3) Иначе первый из examples.
 /// 4) Иначе None → дальше разрулит schema.
-fn pick_example(mt: &MediaType, query: &HashMap<String, String>) -> Option<Value> {
-    // 1) x-mirage-example-param
-    if let Some(param) = &mt.example_param {
-        if let Some(key) = query.get(param) {
-            if let Some(ex) = mt.examples.get(key) {
-                if let Some(v) = &ex.value {
-                    return Some(v.clone());
-                }
+fn pick_example(
+    mt: &MediaType,
+    query: &HashMap<String, String>,
+    headers: &axum::http::HeaderMap,
+) -> Option<Value> {
+    // 1) x-mirage-example-param (query) / x-mirage-example-header (request header)
+    let scenario_key = mt
+        .example_param
+        .as_ref()
+        .and_then(|param| query.get(param))
+        .map(|s| s.as_str())
+        .or_else(|| {
+            mt.example_header
+                .as_ref()
+                .and_then(|header| headers.get(header))
+                .and_then(|v| v.to_str().ok())
+        });
+
+    if let Some(key) = scenario_key {
+        if let Some(ex) = mt.examples.get(key) {
+            if let Some(v) = &ex.value {
+                return Some(v.clone());
             }
         }
     }
@@ -194,42 +783,118 @@ fn pick_example(mt: &MediaType, query: &HashMap<String, String>) -> Option<Value
     None
 }
 
-fn generate_from_schema(schema: &Schema) -> Value {
-    // 1) If there is an enum — take the first value
+/// Threaded through schema generation: the spec (for `$ref` lookups), the seeded RNG (for
+/// per-field variation), and the in-progress `$ref` path (for cycle detection).
+struct GenContext<'a> {
+    spec: &'a OpenApi,
+    rng: Rng,
+    visiting: HashSet<String>,
+}
+
+fn generate_from_schema(schema: &Schema, spec: &OpenApi, seed: u64) -> Value {
+    let mut ctx = GenContext {
+        spec,
+        rng: Rng::new(seed),
+        visiting: HashSet::new(),
+    };
+    generate_value(schema, &mut ctx)
+}
+
+/// Looks up `#/components/schemas/Name`, generating `{}` for a dangling ref and `null` when
+/// `ctx.visiting` shows the ref is already on the current resolution path (a cycle).
+fn resolve_ref(reference: &str, ctx: &mut GenContext) -> Value {
+    if !ctx.visiting.insert(reference.to_string()) {
+        return Value::Null;
+    }
+
+    let name = reference.rsplit('/').next().unwrap_or(reference);
+    let value = match ctx.spec.components.schemas.get(name).cloned() {
+        Some(schema) => generate_value(&schema, ctx),
+        None => Value::Object(serde_json::Map::new()),
+    };
+
+    ctx.visiting.remove(reference);
+    value
+}
+
+fn generate_value(schema: &Schema, ctx: &mut GenContext) -> Value {
+    if let Some(reference) = &schema.reference {
+        return resolve_ref(reference, ctx);
+    }
+
+    if !schema.all_of.is_empty() {
+        let mut merged = serde_json::Map::new();
+        for sub in &schema.all_of {
+            if let Value::Object(fields) = generate_value(sub, ctx) {
+                merged.extend(fields);
+            }
+        }
+        return Value::Object(merged);
+    }
+
+    if let Some(branch) = schema.one_of.first().or_else(|| schema.any_of.first()) {
+        return generate_value(branch, ctx);
+    }
+
+    // An inline default/example always wins over synthesized data.
+    if let Some(value) = schema.default.as_ref().or(schema.example.as_ref()) {
+        return value.clone();
+    }
+
     if !schema.enum_values.is_empty() {
-        return schema.enum_values[0].clone();
+        let index = ctx.rng.next_range(0, schema.enum_values.len() as i64 - 1) as usize;
+        return schema.enum_values[index].clone();
     }
 
     let ty = schema.ty.as_deref().unwrap_or("object");
 
     match ty {
-        "string" => {
-            if let Some(format) = &schema.format {
-                match format.as_str() {
-                    "date-time" => Value::String("2025-01-01T00:00:00Z".to_string()),
-                    "date" => Value::String("2025-01-01".to_string()),
-                    "uuid" => Value::String("00000000-0000-0000-0000-000000000000".to_string()),
-                    _ => Value::String(format!("string({})", format)),
-                }
-            } else {
-                Value::String("string".to_string())
-            }
+        "string" => generate_string(schema, ctx),
+        "number" => {
+            let min = schema
+                .minimum
+                .unwrap_or_else(|| schema.maximum.map_or(0.0, |m| m.min(0.0)));
+            let max = schema.maximum.unwrap_or(min + 1000.0);
+            let value = min + ctx.rng.next_f64() * (max - min).max(0.0);
+            Value::Number(serde_json::Number::from_f64(value).unwrap_or(serde_json::Number::from(0)))
+        }
+        "integer" => {
+            let min = schema
+                .minimum
+                .map(|m| m as i64)
+                .unwrap_or_else(|| schema.maximum.map_or(0, |m| (m as i64).min(0)));
+            let max = schema.maximum.map(|m| m as i64).unwrap_or(min + 1000);
+            Value::Number(serde_json::Number::from(ctx.rng.next_range(min, max)))
         }
-        "number" => Value::Number(serde_json::Number::from_f64(123.45).unwrap()),
-        "integer" => Value::Number(serde_json::Number::from(123)),
-        "boolean" => Value::Bool(true),
+        "boolean" => Value::Bool(ctx.rng.next_f64() < 0.5),
         "array" => {
-            if let Some(item_schema) = &schema.items {
-                Value::Array(vec![generate_from_schema(item_schema)])
-            } else {
-                Value::Array(vec![])
-            }
+            let Some(item_schema) = &schema.items else {
+                return Value::Array(vec![]);
+            };
+
+            let min_items = schema.min_items.unwrap_or(1);
+            let len = match schema.max_items {
+                Some(max_items) => min_items
+                    .max(1)
+                    .min(max_items.max(schema.min_items.unwrap_or(0))),
+                None => min_items.max(1),
+            };
+
+            let items: Vec<Value> = (0..len).map(|_| generate_value(item_schema, ctx)).collect();
+            Value::Array(items)
         }
         "object" => {
             if !schema.properties.is_empty() {
+                // `properties` is a HashMap with randomized per-process iteration order; sort
+                // by key so a given `--seed` assigns the same value to the same field on every
+                // run, rather than whichever field the hash happened to visit first.
+                let mut names: Vec<&String> = schema.properties.keys().collect();
+                names.sort();
+
                 let mut map = serde_json::Map::new();
-                for (name, prop_schema) in &schema.properties {
-                    map.insert(name.clone(), generate_from_schema(prop_schema));
+                for name in names {
+                    let prop_schema = &schema.properties[name];
+                    map.insert(name.clone(), generate_value(prop_schema, ctx));
                 }
                 Value::Object(map)
             } else {
@@ -240,10 +905,76 @@ fn generate_from_schema(schema: &Schema) -> Value {
     }
 }
 
+fn generate_string(schema: &Schema, ctx: &mut GenContext) -> Value {
+    let base = match schema.format.as_deref() {
+        Some("date-time") => "2025-01-01T00:00:00Z".to_string(),
+        Some("date") => "2025-01-01".to_string(),
+        Some("uuid") => format!(
+            "{:08x}-0000-4000-8000-{:012x}",
+            ctx.rng.next_u64() as u32,
+            ctx.rng.next_u64() & 0xFFFF_FFFF_FFFF
+        ),
+        Some("email") => format!("user{}@example.com", ctx.rng.next_range(1, 9999)),
+        Some("uri") => format!("https://example.com/{}", ctx.rng.next_range(1, 9999)),
+        Some("ipv4") => format!(
+            "{}.{}.{}.{}",
+            ctx.rng.next_range(1, 254),
+            ctx.rng.next_range(0, 255),
+            ctx.rng.next_range(0, 255),
+            ctx.rng.next_range(1, 254)
+        ),
+        Some("hostname") => format!("host{}.example.com", ctx.rng.next_range(1, 9999)),
+        Some("byte") => base64_encode(format!("mock{}", ctx.rng.next_range(1, 9999)).as_bytes()),
+        Some(other) => format!("string({})", other),
+        None => "string".to_string(),
+    };
+
+    let min_length = schema.min_length.unwrap_or(0);
+    let mut value = base;
+    while value.len() < min_length {
+        value.push('x');
+    }
+    if let Some(max_length) = schema.max_length {
+        value.truncate(max_length);
+    }
+
+    Value::String(value)
+}
+
+/// Minimal base64 encoder for the `byte`-format string case; avoids pulling in a dedicated
+/// base64 dependency for one call site.
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied();
+        let b2 = chunk.get(2).copied();
+
+        out.push(ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(ALPHABET[(((b0 & 0x03) << 4) | (b1.unwrap_or(0) >> 4)) as usize] as char);
+        out.push(match b1 {
+            Some(b1) => ALPHABET[(((b1 & 0x0F) << 2) | (b2.unwrap_or(0) >> 6)) as usize] as char,
+            None => '=',
+        });
+        out.push(match b2 {
+            Some(b2) => ALPHABET[(b2 & 0x3F) as usize] as char,
+            None => '=',
+        });
+    }
+
+    out
+}
+
 #[allow(clippy::collapsible_if)]
 fn build_body_from_response(
     resp: &Response,
     query: &HashMap<String, String>,
+    headers: &axum::http::HeaderMap,
+    spec: &OpenApi,
+    seed: u64,
 ) -> Option<(Option<BodyKind>, String)> {
     if resp.content.is_empty() {
         return Some((None, "text/plain".to_string()));
@@ -251,8 +982,8 @@ fn build_body_from_response(
 
     // 1) First try JSON: example / examples / schema
     if let Some(mt) = resp.content.get("application/json") {
-        // example / examples (+ x-mirage-example-param)
-        if let Some(example) = pick_example(mt, query) {
+        // example / examples (+ x-mirage-example-param / x-mirage-example-header)
+        if let Some(example) = pick_example(mt, query, headers) {
             return Some((
                 Some(BodyKind::Json(example)),
                 "application/json".to_string(),
@@ -261,15 +992,15 @@ fn build_body_from_response(
 
         // schema without example/examples → generate a mock
         if let Some(schema) = &mt.schema {
-            let value = generate_from_schema(schema);
+            let value = generate_from_schema(schema, spec, seed);
             return Some((Some(BodyKind::Json(value)), "application/json".to_string()));
         }
     }
 
     // 2) Then any other content-type
     if let Some((content_type, mt)) = resp.content.iter().next() {
-        // example / examples (+ x-mirage-example-param)
-        if let Some(example) = pick_example(mt, query) {
+        // example / examples (+ x-mirage-example-param / x-mirage-example-header)
+        if let Some(example) = pick_example(mt, query, headers) {
             if let Some(s) = example.as_str() {
                 return Some((Some(BodyKind::Text(s.to_string())), content_type.clone()));
             } else {
@@ -288,6 +1019,15 @@ fn build_response(
     status: u16,
     body_kind: Option<BodyKind>,
     content_type: String,
+) -> axum::response::Response {
+    build_response_with_headers(status, body_kind, content_type, &[])
+}
+
+fn build_response_with_headers(
+    status: u16,
+    body_kind: Option<BodyKind>,
+    content_type: String,
+    extra_headers: &[(String, String)],
 ) -> axum::response::Response {
     let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
 
@@ -299,6 +1039,15 @@ fn build_response(
         if let Ok(value) = HeaderValue::try_from(content_type.as_str()) {
             headers.insert(name, value);
         }
+
+        for (name, value) in extra_headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::try_from(name.as_str()),
+                HeaderValue::try_from(value.as_str()),
+            ) {
+                headers.insert(name, value);
+            }
+        }
     }
 
     let body_bytes = match body_kind {
@@ -324,3 +1073,375 @@ fn build_not_found_response(method: Method, path: String) -> axum::response::Res
         .body(Body::from(body_string))
         .expect("failed to build 404 response")
 }
+
+#[cfg(test)]
+mod auth_tests {
+    use super::*;
+
+    fn spec_with_api_key(valid_keys: &[&str]) -> OpenApi {
+        let keys = valid_keys
+            .iter()
+            .map(|k| format!("\"{}\"", k))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let yaml = format!(
+            r#"
+openapi: "3.0.0"
+info:
+  title: t
+  version: "1"
+paths:
+  /secret:
+    get:
+      responses:
+        "200": {{}}
+security:
+  - apiKeyAuth: []
+components:
+  securitySchemes:
+    apiKeyAuth:
+      type: apiKey
+      in: header
+      name: X-API-Key
+      x-mirage-valid-keys: [{keys}]
+"#
+        );
+        serde_yaml::from_str(&yaml).expect("valid fixture spec")
+    }
+
+    fn secret_get(spec: &OpenApi) -> Operation {
+        spec.paths["/secret"].get.clone().unwrap()
+    }
+
+    #[test]
+    fn missing_credential_is_401() {
+        let spec = spec_with_api_key(&["good-key"]);
+        let operation = secret_get(&spec);
+        let headers = axum::http::HeaderMap::new();
+        let query = HashMap::new();
+
+        let response = enforce_auth(&spec, &operation, &headers, &query).expect("should be denied");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn invalid_credential_is_403() {
+        let spec = spec_with_api_key(&["good-key"]);
+        let operation = secret_get(&spec);
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-api-key", "wrong-key".parse().unwrap());
+        let query = HashMap::new();
+
+        let response = enforce_auth(&spec, &operation, &headers, &query).expect("should be denied");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn valid_credential_is_allowed() {
+        let spec = spec_with_api_key(&["good-key"]);
+        let operation = secret_get(&spec);
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-api-key", "good-key".parse().unwrap());
+        let query = HashMap::new();
+
+        assert!(enforce_auth(&spec, &operation, &headers, &query).is_none());
+    }
+}
+
+#[cfg(test)]
+mod stateful_tests {
+    use super::*;
+
+    fn spec() -> OpenApi {
+        let yaml = r#"
+openapi: "3.0.0"
+info:
+  title: t
+  version: "1"
+paths:
+  /items:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              type: object
+              properties:
+                id:
+                  type: string
+                name:
+                  type: string
+      responses:
+        "201": {}
+  /items/{id}:
+    get:
+      responses:
+        "200": {}
+    delete:
+      responses:
+        "204": {}
+"#;
+        serde_yaml::from_str(yaml).expect("valid fixture spec")
+    }
+
+    fn test_state() -> AppState {
+        AppState {
+            spec: spec(),
+            stateful: true,
+            store: Arc::new(Store::new()),
+            jsonrpc_prefix: None,
+            seed: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip_and_404s() {
+        let state = test_state();
+
+        let post_op = state.spec.paths["/items"].post.clone().unwrap();
+        let create_req = Request::builder()
+            .method(Method::POST)
+            .uri("/items")
+            .body(Body::from(r#"{"name":"widget"}"#))
+            .unwrap();
+        let created = handle_stateful(&state, "/items", &post_op, &Method::POST, "/items", create_req)
+            .await
+            .expect("POST should be handled");
+        assert_eq!(created.status(), StatusCode::CREATED);
+
+        let body = axum::body::to_bytes(created.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created_value: Value = serde_json::from_slice(&body).unwrap();
+        let id = created_value["id"].as_str().unwrap().to_string();
+        let path = format!("/items/{}", id);
+
+        let get_op = state.spec.paths["/items/{id}"].get.clone().unwrap();
+        let get_req = Request::builder()
+            .method(Method::GET)
+            .uri(&path)
+            .body(Body::empty())
+            .unwrap();
+        let found = handle_stateful(&state, "/items/{id}", &get_op, &Method::GET, &path, get_req)
+            .await
+            .expect("GET should be handled");
+        assert_eq!(found.status(), StatusCode::OK);
+
+        let delete_op = state.spec.paths["/items/{id}"].delete.clone().unwrap();
+        let delete_req = Request::builder()
+            .method(Method::DELETE)
+            .uri(&path)
+            .body(Body::empty())
+            .unwrap();
+        let deleted = handle_stateful(&state, "/items/{id}", &delete_op, &Method::DELETE, &path, delete_req)
+            .await
+            .expect("DELETE should be handled");
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+
+        // GET of the now-removed id is a real 404, not a replayed spec example.
+        let get_req2 = Request::builder()
+            .method(Method::GET)
+            .uri(&path)
+            .body(Body::empty())
+            .unwrap();
+        let missing = handle_stateful(&state, "/items/{id}", &get_op, &Method::GET, &path, get_req2)
+            .await
+            .expect("GET of missing id should be handled");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        // DELETE of an already-removed id is also a 404.
+        let delete_req2 = Request::builder()
+            .method(Method::DELETE)
+            .uri(&path)
+            .body(Body::empty())
+            .unwrap();
+        let missing_delete =
+            handle_stateful(&state, "/items/{id}", &delete_op, &Method::DELETE, &path, delete_req2)
+                .await
+                .expect("DELETE of missing id should be handled");
+        assert_eq!(missing_delete.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod ref_tests {
+    use super::*;
+
+    fn spec_with_cycle() -> OpenApi {
+        let yaml = r#"
+openapi: "3.0.0"
+info:
+  title: t
+  version: "1"
+components:
+  schemas:
+    Node:
+      type: object
+      properties:
+        name:
+          type: string
+        child:
+          $ref: '#/components/schemas/Node'
+"#;
+        serde_yaml::from_str(yaml).expect("valid fixture spec")
+    }
+
+    #[test]
+    fn ref_cycle_terminates_with_null_instead_of_recursing_forever() {
+        let spec = spec_with_cycle();
+        let schema: Schema = serde_yaml::from_str("$ref: '#/components/schemas/Node'").unwrap();
+
+        let value = generate_from_schema(&schema, &spec, 1);
+
+        assert_eq!(value["child"], Value::Null);
+        assert!(value["name"].is_string());
+    }
+
+    #[test]
+    fn all_of_merges_subschemas_into_one_object() {
+        let spec: OpenApi = serde_yaml::from_str(
+            "openapi: \"3.0.0\"\ninfo:\n  title: t\n  version: \"1\"\n",
+        )
+        .unwrap();
+        let schema: Schema = serde_yaml::from_str(
+            r#"
+allOf:
+  - type: object
+    properties:
+      a: { type: string }
+  - type: object
+    properties:
+      b: { type: integer }
+"#,
+        )
+        .unwrap();
+
+        let value = generate_from_schema(&schema, &spec, 1);
+
+        assert!(value["a"].is_string());
+        assert!(value["b"].is_number());
+    }
+}
+
+#[cfg(test)]
+mod jsonrpc_tests {
+    use super::*;
+
+    fn operation() -> Operation {
+        let yaml = r#"
+responses:
+  "200":
+    content:
+      application/json:
+        examples:
+          add:
+            value: 3
+"#;
+        serde_yaml::from_str(yaml).expect("valid fixture operation")
+    }
+
+    #[test]
+    fn known_method_call_gets_its_result() {
+        let op = operation();
+        let call = serde_json::json!({ "jsonrpc": "2.0", "method": "add", "id": 1 });
+
+        let response = dispatch_jsonrpc_call(&call, &op).expect("a call always gets a response");
+        assert_eq!(response["result"], serde_json::json!(3));
+        assert_eq!(response["id"], serde_json::json!(1));
+    }
+
+    #[test]
+    fn notification_without_id_gets_no_response() {
+        let op = operation();
+        let call = serde_json::json!({ "jsonrpc": "2.0", "method": "add" });
+
+        assert!(dispatch_jsonrpc_call(&call, &op).is_none());
+    }
+
+    #[test]
+    fn call_with_id_but_no_method_is_invalid_request_not_dropped() {
+        let op = operation();
+        let call = serde_json::json!({ "jsonrpc": "2.0", "id": 7 });
+
+        let response = dispatch_jsonrpc_call(&call, &op).expect("id-bearing call must get a response");
+        assert_eq!(response["error"]["code"], serde_json::json!(-32600));
+        assert_eq!(response["id"], serde_json::json!(7));
+    }
+
+    #[tokio::test]
+    async fn batch_drops_notifications_but_keeps_call_responses() {
+        let op = operation();
+        let body = serde_json::json!([
+            { "jsonrpc": "2.0", "method": "add", "id": 1 },
+            { "jsonrpc": "2.0", "method": "add" },
+        ]);
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/rpc")
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap();
+
+        let response = handle_jsonrpc(&op, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(results.as_array().map(Vec::len), Some(1));
+        assert_eq!(results[0]["id"], serde_json::json!(1));
+    }
+}
+
+#[cfg(test)]
+mod generation_tests {
+    use super::*;
+
+    fn spec() -> OpenApi {
+        serde_yaml::from_str("openapi: \"3.0.0\"\ninfo:\n  title: t\n  version: \"1\"\n").unwrap()
+    }
+
+    fn user_schema() -> Schema {
+        // Field declaration order is deliberately not alphabetical, so this also guards
+        // against regressing to HashMap iteration order for determinism.
+        serde_yaml::from_str(
+            r#"
+type: object
+properties:
+  role:
+    type: string
+    enum: ["admin", "member", "guest"]
+  id:
+    type: string
+    format: uuid
+  age:
+    type: integer
+    minimum: 0
+    maximum: 99
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn same_seed_reproduces_byte_for_byte() {
+        let spec = spec();
+
+        // Two separately parsed schemas, so each `properties` map has its own hash order.
+        let first = generate_from_schema(&user_schema(), &spec, 42);
+        let second = generate_from_schema(&user_schema(), &spec, 42);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seeds_can_diverge() {
+        let spec = spec();
+        let schema = user_schema();
+
+        let a = generate_from_schema(&schema, &spec, 1);
+        let b = generate_from_schema(&schema, &spec, 2);
+
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,93 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// In-memory CRUD backing store used by `--stateful` mode, keyed by collection
+/// (the path template with any trailing `{id}` segment stripped, e.g. `/users`).
+#[derive(Default)]
+pub struct Store {
+    collections: RwLock<HashMap<String, Vec<Value>>>,
+    next_id: AtomicU64,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Monotonically increasing counter used to mint ids for newly inserted resources.
+    pub fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn list(&self, collection: &str) -> Vec<Value> {
+        self.collections
+            .read()
+            .unwrap()
+            .get(collection)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, collection: &str, id: &str) -> Option<Value> {
+        self.collections
+            .read()
+            .unwrap()
+            .get(collection)?
+            .iter()
+            .find(|item| item_id(item).as_deref() == Some(id))
+            .cloned()
+    }
+
+    pub fn insert(&self, collection: &str, item: Value) {
+        self.collections
+            .write()
+            .unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .push(item);
+    }
+
+    pub fn merge(&self, collection: &str, id: &str, patch: &Value) -> Option<Value> {
+        let mut collections = self.collections.write().unwrap();
+        let item = collections
+            .get_mut(collection)?
+            .iter_mut()
+            .find(|item| item_id(item).as_deref() == Some(id))?;
+        merge_json(item, patch);
+        Some(item.clone())
+    }
+
+    pub fn remove(&self, collection: &str, id: &str) -> bool {
+        let Some(items) = self.collections.write().unwrap().get_mut(collection).map(|v| {
+            let before = v.len();
+            v.retain(|item| item_id(item).as_deref() != Some(id));
+            before != v.len()
+        }) else {
+            return false;
+        };
+        items
+    }
+}
+
+fn item_id(item: &Value) -> Option<String> {
+    match item.get("id")? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn merge_json(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target_map), Value::Object(patch_map)) => {
+            for (key, value) in patch_map {
+                merge_json(target_map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => {
+            *target = patch.clone();
+        }
+    }
+}